@@ -0,0 +1,198 @@
+//! Pluggable codegen backends.
+//!
+//! `State::write` used to hardcode emitting C (`output.c`,
+//! `include_str!("yamero.c")`, `p((void*)&v_item, t_item)`). `Backend`
+//! factors that emission out from the walk that builds `constants`/
+//! `types`/`runtime_queue`/`print_queue`, so the same front end can target
+//! either textual C ([`CBackend`]) or LLVM IR ([`LlvmBackend`]) without
+//! duplicating that walk.
+
+use std::collections::HashMap;
+
+pub trait Backend {
+    /// File extension (without the dot) this backend's output belongs
+    /// under, e.g. `"c"` or `"ll"`.
+    fn extension(&self) -> &'static str;
+
+    fn preamble(&mut self);
+    fn emit_constant(&mut self, id: usize, kind: &str, value: &str);
+    fn emit_kind(&mut self, id: usize, kind: u8);
+    fn begin_body(&mut self);
+    fn emit_runtime_assign(&mut self, id: usize, expr: &str);
+    fn emit_print(&mut self, id: usize);
+    fn finish(self: Box<Self>) -> String;
+}
+
+/// The original backend: emits the `output.c` text `State::write` always
+/// produced before backends existed.
+#[derive(Default)]
+pub struct CBackend {
+    out: String,
+}
+
+impl Backend for CBackend {
+    fn extension(&self) -> &'static str {
+        "c"
+    }
+
+    fn preamble(&mut self) {
+        self.out.push_str(include_str!("yamero.c"));
+        self.out.push('\n');
+    }
+
+    fn emit_constant(&mut self, id: usize, kind: &str, value: &str) {
+        self.out.push_str(&format!("{kind} v_{id} = {value};\n"));
+    }
+
+    fn emit_kind(&mut self, id: usize, kind: u8) {
+        self.out.push_str(&format!("const Kind t_{id} = {kind};\n"));
+    }
+
+    fn begin_body(&mut self) {
+        self.out.push_str("int main(void) {\n");
+    }
+
+    fn emit_runtime_assign(&mut self, id: usize, expr: &str) {
+        self.out.push_str(&format!("v_{id} = {expr};\n"));
+    }
+
+    fn emit_print(&mut self, id: usize) {
+        self.out
+            .push_str(&format!("p((void*)&v_{id}, t_{id});\n"));
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        let mut out = self.out;
+        out.push_str("return 0;}\n");
+        out
+    }
+}
+
+/// The LLVM type a constant was actually declared with, so later
+/// `emit_runtime_assign`/`emit_print` calls can reference it correctly
+/// instead of assuming every id is an `i64`.
+#[derive(Clone, Copy)]
+enum LlvmTy {
+    I64,
+    I8,
+    /// A `char*` constant: an array global of this many bytes (its text
+    /// plus the trailing `\00`), not a pointer-typed one.
+    Str(usize),
+}
+
+impl LlvmTy {
+    fn name(self) -> String {
+        match self {
+            LlvmTy::I64 => "i64".to_string(),
+            LlvmTy::I8 => "i8".to_string(),
+            LlvmTy::Str(len) => format!("[{len} x i8]"),
+        }
+    }
+}
+
+/// Lowers the same numbered constants and queues to textual LLVM IR instead
+/// of C: each constant becomes a global, each `runtime_queue` entry a store
+/// into it, and each `print_queue` entry a call into a small `touka_print`
+/// runtime.
+#[derive(Default)]
+pub struct LlvmBackend {
+    globals: String,
+    body: String,
+    types: HashMap<usize, LlvmTy>,
+}
+
+impl Backend for LlvmBackend {
+    fn extension(&self) -> &'static str {
+        "ll"
+    }
+
+    fn preamble(&mut self) {
+        self.globals
+            .push_str("; touka LLVM backend\ndeclare void @touka_print(i8*, i32)\n\n");
+    }
+
+    fn emit_constant(&mut self, id: usize, kind: &str, value: &str) {
+        if kind == "char*" {
+            let raw = unquote(value);
+            let len = raw.len() + 1;
+            self.types.insert(id, LlvmTy::Str(len));
+            self.globals.push_str(&format!(
+                "@v_{id} = global [{len} x i8] c\"{}\\00\"\n",
+                escape_llvm_string(&raw)
+            ));
+            return;
+        }
+
+        let ty = if kind == "char" { LlvmTy::I8 } else { LlvmTy::I64 };
+        self.types.insert(id, ty);
+        self.globals
+            .push_str(&format!("@v_{id} = global {} {value}\n", ty.name()));
+    }
+
+    fn emit_kind(&mut self, id: usize, kind: u8) {
+        self.globals
+            .push_str(&format!("@t_{id} = constant i8 {kind}\n"));
+    }
+
+    fn begin_body(&mut self) {
+        self.body.push_str("define i32 @main() {\nentry:\n");
+    }
+
+    fn emit_runtime_assign(&mut self, id: usize, expr: &str) {
+        let ty = self.types.get(&id).copied().unwrap_or(LlvmTy::I64).name();
+        self.body
+            .push_str(&format!("  store {ty} {expr}, {ty}* @v_{id}\n"));
+    }
+
+    fn emit_print(&mut self, id: usize) {
+        match self.types.get(&id).copied().unwrap_or(LlvmTy::I64) {
+            LlvmTy::Str(len) => {
+                let ty = LlvmTy::Str(len).name();
+                self.body.push_str(&format!(
+                    "  call void @touka_print(i8* getelementptr inbounds ({ty}, {ty}* @v_{id}, i32 0, i32 0), i32 0)\n"
+                ));
+            }
+            scalar => {
+                let ty = scalar.name();
+                self.body.push_str(&format!(
+                    "  call void @touka_print(i8* bitcast ({ty}* @v_{id} to i8*), i32 0)\n"
+                ));
+            }
+        }
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        let mut out = self.globals;
+        out.push('\n');
+        out.push_str(&self.body);
+        out.push_str("  ret i32 0\n}\n");
+        out
+    }
+}
+
+/// Undoes `gen`'s `format!("{:?}", s.value)` debug-quoting to recover a
+/// `char*` constant's raw text before re-encoding it as an LLVM string
+/// literal.
+fn unquote(debug_quoted: &str) -> String {
+    debug_quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(debug_quoted)
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\")
+}
+
+/// Encodes `raw` as the body of an LLVM `c"..."` string constant: printable
+/// ASCII passes through, everything else (including `"` and `\`) becomes a
+/// `\XX` hex-escaped byte.
+fn escape_llvm_string(raw: &str) -> String {
+    let mut out = String::new();
+    for byte in raw.bytes() {
+        if matches!(byte, 0x20..=0x7e) && byte != b'"' && byte != b'\\' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("\\{byte:02X}"));
+        }
+    }
+    out
+}
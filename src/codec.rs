@@ -0,0 +1,465 @@
+//! Binary (de)serialization of the AST, for a compiled-artifact cache.
+//!
+//! `encode`/`decode` turn an `AstRoot` into a compact CBOR byte string and
+//! back, so a front end can cache a parsed program and skip re-parsing it
+//! when the source hasn't changed. Every `Term` variant maps to a CBOR
+//! array whose first element is a small integer discriminant and whose
+//! remaining elements are its fields, recursively encoded the same way
+//! (ints as CBOR integers, strings as CBOR text, `Binary`'s `BinaryOp` as
+//! an integer tag alongside its two operands, `If`'s three branches in
+//! order).
+
+use std::fmt;
+
+use crate::ast::{Binary, BinaryOp, Bool, File as AstRoot, If, Int, Let, Print, Str, Term, Var};
+
+#[derive(Debug)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    UnknownDiscriminant(u64),
+    MalformedUtf8,
+    WrongMajorType { expected: &'static str, found: u8 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            DecodeError::UnknownDiscriminant(d) => write!(f, "unknown term discriminant {d}"),
+            DecodeError::MalformedUtf8 => write!(f, "malformed utf-8 in encoded string"),
+            DecodeError::WrongMajorType { expected, found } => {
+                write!(f, "expected a CBOR {expected}, found major type {found}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+type DecodeResult<T> = Result<T, DecodeError>;
+
+// --- Term discriminants ---------------------------------------------------
+
+const D_ERROR: u64 = 0;
+const D_INT: u64 = 1;
+const D_STR: u64 = 2;
+const D_BOOL: u64 = 3;
+const D_CALL: u64 = 4;
+const D_BINARY: u64 = 5;
+const D_FUNCTION: u64 = 6;
+const D_LET: u64 = 7;
+const D_IF: u64 = 8;
+const D_PRINT: u64 = 9;
+const D_FIRST: u64 = 10;
+const D_SECOND: u64 = 11;
+const D_TUPLE: u64 = 12;
+const D_VAR: u64 = 13;
+
+pub fn encode(root: &AstRoot) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_str(&root.name, &mut out);
+    encode_term(&root.expression, &mut out);
+    out
+}
+
+pub fn decode(bytes: &[u8]) -> DecodeResult<AstRoot> {
+    let mut cursor = 0usize;
+    let name = decode_str(bytes, &mut cursor)?;
+    let expression = decode_term(bytes, &mut cursor)?;
+    Ok(AstRoot { name, expression })
+}
+
+fn encode_term(term: &Term, out: &mut Vec<u8>) {
+    match term {
+        Term::Error(e) => {
+            encode_array_header(2, out);
+            encode_uint(D_ERROR, out);
+            encode_str(&e.message, out);
+        }
+        Term::Int(i) => {
+            encode_array_header(2, out);
+            encode_uint(D_INT, out);
+            encode_int(i.value, out);
+        }
+        Term::Str(s) => {
+            encode_array_header(2, out);
+            encode_uint(D_STR, out);
+            encode_str(&s.value, out);
+        }
+        Term::Bool(b) => {
+            encode_array_header(2, out);
+            encode_uint(D_BOOL, out);
+            encode_uint(if b.value { 1 } else { 0 }, out);
+        }
+        Term::Call(c) => {
+            encode_array_header(3, out);
+            encode_uint(D_CALL, out);
+            encode_term(&c.callee, out);
+            encode_array_header(c.arguments.len() as u64, out);
+            for arg in &c.arguments {
+                encode_term(arg, out);
+            }
+        }
+        Term::Binary(b) => {
+            encode_array_header(4, out);
+            encode_uint(D_BINARY, out);
+            encode_uint(binary_op_tag(b.op), out);
+            encode_term(&b.lhs, out);
+            encode_term(&b.rhs, out);
+        }
+        Term::Function(f) => {
+            encode_array_header(3, out);
+            encode_uint(D_FUNCTION, out);
+            encode_array_header(f.parameters.len() as u64, out);
+            for p in &f.parameters {
+                encode_str(p, out);
+            }
+            encode_term(&f.value, out);
+        }
+        Term::Let(l) => {
+            encode_array_header(4, out);
+            encode_uint(D_LET, out);
+            encode_str(&l.name, out);
+            encode_term(&l.value, out);
+            encode_term(&l.next, out);
+        }
+        Term::If(c) => {
+            encode_array_header(4, out);
+            encode_uint(D_IF, out);
+            encode_term(&c.condition, out);
+            encode_term(&c.then, out);
+            encode_term(&c.otherwise, out);
+        }
+        Term::Print(p) => {
+            encode_array_header(2, out);
+            encode_uint(D_PRINT, out);
+            encode_term(&p.value, out);
+        }
+        Term::First(f) => {
+            encode_array_header(2, out);
+            encode_uint(D_FIRST, out);
+            encode_term(&f.value, out);
+        }
+        Term::Second(s) => {
+            encode_array_header(2, out);
+            encode_uint(D_SECOND, out);
+            encode_term(&s.value, out);
+        }
+        Term::Tuple(t) => {
+            encode_array_header(3, out);
+            encode_uint(D_TUPLE, out);
+            encode_term(&t.first, out);
+            encode_term(&t.second, out);
+        }
+        Term::Var(v) => {
+            encode_array_header(2, out);
+            encode_uint(D_VAR, out);
+            encode_str(&v.name, out);
+        }
+    }
+}
+
+fn decode_term(bytes: &[u8], cursor: &mut usize) -> DecodeResult<Term> {
+    let len = decode_array_header(bytes, cursor)?;
+    let discriminant = decode_uint(bytes, cursor)?;
+
+    let term = match discriminant {
+        D_ERROR => Term::Error(crate::ast::Error {
+            message: decode_str(bytes, cursor)?,
+        }),
+        D_INT => Term::Int(Int {
+            value: decode_int(bytes, cursor)?,
+        }),
+        D_STR => Term::Str(Str {
+            value: decode_str(bytes, cursor)?,
+        }),
+        D_BOOL => Term::Bool(Bool {
+            value: decode_uint(bytes, cursor)? != 0,
+        }),
+        D_CALL => {
+            let callee = Box::new(decode_term(bytes, cursor)?);
+            let argc = decode_array_header(bytes, cursor)?;
+            let mut arguments = Vec::with_capacity(argc as usize);
+            for _ in 0..argc {
+                arguments.push(decode_term(bytes, cursor)?);
+            }
+            Term::Call(crate::ast::Call { callee, arguments })
+        }
+        D_BINARY => {
+            let op = binary_op_from_tag(decode_uint(bytes, cursor)?)?;
+            let lhs = Box::new(decode_term(bytes, cursor)?);
+            let rhs = Box::new(decode_term(bytes, cursor)?);
+            Term::Binary(Binary { lhs, op, rhs })
+        }
+        D_FUNCTION => {
+            let paramc = decode_array_header(bytes, cursor)?;
+            let mut parameters = Vec::with_capacity(paramc as usize);
+            for _ in 0..paramc {
+                parameters.push(decode_str(bytes, cursor)?);
+            }
+            let value = Box::new(decode_term(bytes, cursor)?);
+            Term::Function(crate::ast::Function { parameters, value })
+        }
+        D_LET => {
+            let name = decode_str(bytes, cursor)?;
+            let value = Box::new(decode_term(bytes, cursor)?);
+            let next = Box::new(decode_term(bytes, cursor)?);
+            Term::Let(Let { name, value, next })
+        }
+        D_IF => {
+            let condition = Box::new(decode_term(bytes, cursor)?);
+            let then = Box::new(decode_term(bytes, cursor)?);
+            let otherwise = Box::new(decode_term(bytes, cursor)?);
+            Term::If(If {
+                condition,
+                then,
+                otherwise,
+            })
+        }
+        D_PRINT => Term::Print(Print {
+            value: Box::new(decode_term(bytes, cursor)?),
+        }),
+        D_FIRST => Term::First(crate::ast::First {
+            value: Box::new(decode_term(bytes, cursor)?),
+        }),
+        D_SECOND => Term::Second(crate::ast::Second {
+            value: Box::new(decode_term(bytes, cursor)?),
+        }),
+        D_TUPLE => {
+            let first = Box::new(decode_term(bytes, cursor)?);
+            let second = Box::new(decode_term(bytes, cursor)?);
+            Term::Tuple(crate::ast::Tuple { first, second })
+        }
+        D_VAR => Term::Var(Var {
+            name: decode_str(bytes, cursor)?,
+        }),
+        other => return Err(DecodeError::UnknownDiscriminant(other)),
+    };
+
+    let _ = len;
+    Ok(term)
+}
+
+fn binary_op_tag(op: BinaryOp) -> u64 {
+    match op {
+        BinaryOp::Add => 0,
+        BinaryOp::Sub => 1,
+        BinaryOp::Mul => 2,
+        BinaryOp::Div => 3,
+        BinaryOp::Rem => 4,
+        BinaryOp::Eq => 5,
+        BinaryOp::Neq => 6,
+        BinaryOp::Lt => 7,
+        BinaryOp::Gt => 8,
+        BinaryOp::Lte => 9,
+        BinaryOp::Gte => 10,
+        BinaryOp::And => 11,
+        BinaryOp::Or => 12,
+    }
+}
+
+fn binary_op_from_tag(tag: u64) -> DecodeResult<BinaryOp> {
+    Ok(match tag {
+        0 => BinaryOp::Add,
+        1 => BinaryOp::Sub,
+        2 => BinaryOp::Mul,
+        3 => BinaryOp::Div,
+        4 => BinaryOp::Rem,
+        5 => BinaryOp::Eq,
+        6 => BinaryOp::Neq,
+        7 => BinaryOp::Lt,
+        8 => BinaryOp::Gt,
+        9 => BinaryOp::Lte,
+        10 => BinaryOp::Gte,
+        11 => BinaryOp::And,
+        12 => BinaryOp::Or,
+        other => return Err(DecodeError::UnknownDiscriminant(other)),
+    })
+}
+
+// --- Minimal CBOR primitives ----------------------------------------------
+//
+// Only what the AST needs: unsigned/negative integers (major types 0/1),
+// text strings (major type 3) and arrays (major type 4).
+
+fn encode_array_header(len: u64, out: &mut Vec<u8>) {
+    encode_major(4, len, out);
+}
+
+fn encode_uint(value: u64, out: &mut Vec<u8>) {
+    encode_major(0, value, out);
+}
+
+fn encode_int(value: i64, out: &mut Vec<u8>) {
+    if value >= 0 {
+        encode_major(0, value as u64, out);
+    } else {
+        encode_major(1, (-1 - value) as u64, out);
+    }
+}
+
+fn encode_str(value: &str, out: &mut Vec<u8>) {
+    encode_major(3, value.len() as u64, out);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn encode_major(major: u8, value: u64, out: &mut Vec<u8>) {
+    let prefix = major << 5;
+    if value < 24 {
+        out.push(prefix | value as u8);
+    } else if value <= u8::MAX as u64 {
+        out.push(prefix | 24);
+        out.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        out.push(prefix | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        out.push(prefix | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(prefix | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn decode_major(bytes: &[u8], cursor: &mut usize, expected: u8) -> DecodeResult<u64> {
+    let head = *bytes.get(*cursor).ok_or(DecodeError::UnexpectedEnd)?;
+    let major = head >> 5;
+    let info = head & 0x1f;
+
+    if major != expected {
+        return Err(DecodeError::WrongMajorType {
+            expected: major_name(expected),
+            found: major,
+        });
+    }
+
+    *cursor += 1;
+
+    let value = match info {
+        0..=23 => info as u64,
+        24 => {
+            let b = read_bytes::<1>(bytes, cursor)?;
+            u8::from_be_bytes(b) as u64
+        }
+        25 => {
+            let b = read_bytes::<2>(bytes, cursor)?;
+            u16::from_be_bytes(b) as u64
+        }
+        26 => {
+            let b = read_bytes::<4>(bytes, cursor)?;
+            u32::from_be_bytes(b) as u64
+        }
+        27 => {
+            let b = read_bytes::<8>(bytes, cursor)?;
+            u64::from_be_bytes(b)
+        }
+        _ => return Err(DecodeError::UnexpectedEnd),
+    };
+
+    Ok(value)
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8], cursor: &mut usize) -> DecodeResult<[u8; N]> {
+    let end = *cursor + N;
+    let slice = bytes.get(*cursor..end).ok_or(DecodeError::UnexpectedEnd)?;
+    *cursor = end;
+    let mut buf = [0u8; N];
+    buf.copy_from_slice(slice);
+    Ok(buf)
+}
+
+fn major_name(major: u8) -> &'static str {
+    match major {
+        0 => "unsigned integer",
+        1 => "negative integer",
+        3 => "text string",
+        4 => "array",
+        _ => "value",
+    }
+}
+
+fn decode_array_header(bytes: &[u8], cursor: &mut usize) -> DecodeResult<u64> {
+    decode_major(bytes, cursor, 4)
+}
+
+fn decode_uint(bytes: &[u8], cursor: &mut usize) -> DecodeResult<u64> {
+    decode_major(bytes, cursor, 0)
+}
+
+fn decode_int(bytes: &[u8], cursor: &mut usize) -> DecodeResult<i64> {
+    let head = *bytes.get(*cursor).ok_or(DecodeError::UnexpectedEnd)?;
+    if head >> 5 == 1 {
+        let n = decode_major(bytes, cursor, 1)?;
+        Ok(-1 - n as i64)
+    } else {
+        Ok(decode_uint(bytes, cursor)? as i64)
+    }
+}
+
+fn decode_str(bytes: &[u8], cursor: &mut usize) -> DecodeResult<String> {
+    let len = decode_major(bytes, cursor, 3)? as usize;
+    let end = *cursor + len;
+    let slice = bytes.get(*cursor..end).ok_or(DecodeError::UnexpectedEnd)?;
+    *cursor = end;
+    String::from_utf8(slice.to_vec()).map_err(|_| DecodeError::MalformedUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Binary, BinaryOp, File as AstRoot, If, Int, Let, Print, Str, Term, Var};
+
+    #[test]
+    fn round_trips_a_program() {
+        let root = AstRoot {
+            name: "scratch.rinha".to_string(),
+            expression: Term::Let(Let {
+                name: "x".to_string(),
+                value: Box::new(Term::Binary(Binary {
+                    lhs: Box::new(Term::Int(Int { value: 1 })),
+                    op: BinaryOp::Add,
+                    rhs: Box::new(Term::Int(Int { value: 2 })),
+                })),
+                next: Box::new(Term::If(If {
+                    condition: Box::new(Term::Binary(Binary {
+                        lhs: Box::new(Term::Var(Var {
+                            name: "x".to_string(),
+                        })),
+                        op: BinaryOp::Gt,
+                        rhs: Box::new(Term::Int(Int { value: 0 })),
+                    })),
+                    then: Box::new(Term::Print(Print {
+                        value: Box::new(Term::Str(Str {
+                            value: "positive".to_string(),
+                        })),
+                    })),
+                    otherwise: Box::new(Term::Print(Print {
+                        value: Box::new(Term::Str(Str {
+                            value: "not positive".to_string(),
+                        })),
+                    })),
+                })),
+            }),
+        };
+
+        let encoded = encode(&root);
+        let decoded = decode(&encoded).expect("round-trip decode");
+
+        assert_eq!(decoded, root);
+    }
+
+    #[test]
+    fn rejects_unknown_discriminants() {
+        let mut bytes = Vec::new();
+        encode_str("scratch.rinha", &mut bytes);
+        encode_array_header(2, &mut bytes);
+        encode_uint(255, &mut bytes);
+        encode_int(1, &mut bytes);
+
+        assert!(matches!(
+            decode(&bytes),
+            Err(DecodeError::UnknownDiscriminant(255))
+        ));
+    }
+}
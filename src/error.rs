@@ -0,0 +1,134 @@
+//! Structured diagnostics with source spans.
+//!
+//! Replaces the `panic!`s that used to kill the process on the first
+//! mistake `inspect`/`typecheck` ran into. A [`Diagnostic`] doesn't abort
+//! anything by itself — producers push one (or several) and carry on, and a
+//! caller renders the whole batch at once with [`render`].
+
+use crate::ast::Term;
+
+/// A byte-offset range into the original source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Sentinel for "no real span available". Reported honestly as an
+    /// unknown location by `render_label` rather than rendered as if it
+    /// pointed at byte 0 of line 1.
+    pub const UNKNOWN: Span = Span {
+        start: usize::MAX,
+        end: usize::MAX,
+    };
+
+    /// Real spans come from the parser. Until `ast::Term` carries one on
+    /// every variant, this is the best a diagnostic producer can do.
+    pub fn of(term: &Term) -> Span {
+        let _ = term;
+        Span::UNKNOWN
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        *self == Span::UNKNOWN
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Label {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub primary: Label,
+    pub notes: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>, primary: Label) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            primary,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, note: Label) -> Self {
+        self.notes.push(note);
+        self
+    }
+}
+
+/// Renders a batch of diagnostics against `source`, printing a
+/// caret-underlined snippet of the offending line for each one, with any
+/// secondary labels (e.g. the operator next to a bad operand) underlined
+/// too.
+pub fn render(source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+
+    for diagnostic in diagnostics {
+        out.push_str(&format!(
+            "error[{}]: {}\n",
+            diagnostic.code, diagnostic.message
+        ));
+        render_label(source, &diagnostic.primary, &mut out);
+        for note in &diagnostic.notes {
+            render_label(source, note, &mut out);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn render_label(source: &str, label: &Label, out: &mut String) {
+    if label.span.is_unknown() {
+        out.push_str("  --> <unknown location>\n");
+        out.push_str(&format!("    | {}\n", label.message));
+        return;
+    }
+
+    let (line_no, line, col) = locate(source, label.span.start);
+    let underline_len = (label.span.end.saturating_sub(label.span.start)).max(1);
+
+    out.push_str(&format!("  --> line {line_no}\n"));
+    out.push_str(&format!("   | {line}\n"));
+    out.push_str(&format!(
+        "   | {}{} {}\n",
+        " ".repeat(col),
+        "^".repeat(underline_len),
+        label.message
+    ));
+}
+
+fn locate(source: &str, offset: usize) -> (usize, &str, usize) {
+    let mut consumed = 0;
+    for (i, line) in source.lines().enumerate() {
+        let end = consumed + line.len();
+        if offset <= end {
+            return (i + 1, line, offset - consumed);
+        }
+        consumed = end + 1;
+    }
+
+    (
+        source.lines().count().max(1),
+        source.lines().last().unwrap_or(""),
+        0,
+    )
+}
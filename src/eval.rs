@@ -0,0 +1,213 @@
+//! A tree-walking interpreter, run alongside (not instead of) the C backend.
+//!
+//! `gen::State` only ever emits target code (C, or LLVM IR through
+//! `backend`) — running a program means writing `output.c`/`output.ll` and
+//! compiling it. `eval` interprets the same `AstRoot`
+//! directly and produces a [`Value`], so a REPL or test can run a program
+//! instantly without shelling out to a C compiler.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::ast::{BinaryOp, File as AstRoot, Term};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Default)]
+struct Env {
+    frames: Vec<HashMap<String, Value>>,
+}
+
+impl Env {
+    fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    fn bind(&mut self, name: String, value: Value) {
+        if self.frames.is_empty() {
+            self.frames.push(HashMap::new());
+        }
+        self.frames.last_mut().unwrap().insert(name, value);
+    }
+
+    fn resolve(&self, name: &str) -> Option<&Value> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+}
+
+pub fn eval(root: &AstRoot, out: &mut impl Write) -> Value {
+    let mut env = Env::default();
+    eval_term(&root.expression, &mut env, out)
+}
+
+fn eval_term(term: &Term, env: &mut Env, out: &mut impl Write) -> Value {
+    match term {
+        Term::Int(i) => Value::Int(i.value),
+        Term::Str(s) => Value::Str(s.value.clone()),
+        Term::Bool(b) => Value::Bool(b.value),
+
+        Term::Binary(b) => {
+            let lhs = eval_term(&b.lhs, env, out);
+            let rhs = eval_term(&b.rhs, env, out);
+            eval_binary(b.op, lhs, rhs)
+        }
+
+        Term::If(comp) => match eval_term(&comp.condition, env, out) {
+            Value::Bool(true) => eval_term(&comp.then, env, out),
+            Value::Bool(false) => eval_term(&comp.otherwise, env, out),
+            other => panic!("If condition must evaluate to a bool, found {other:?}"),
+        },
+
+        Term::Let(l) => {
+            let value = eval_term(&l.value, env, out);
+            env.bind(l.name.clone(), value);
+            eval_term(&l.next, env, out)
+        }
+
+        Term::Var(v) => env
+            .resolve(&v.name)
+            .unwrap_or_else(|| panic!("Var => unbound name {:?}", v.name))
+            .clone(),
+
+        Term::Print(p) => {
+            let value = eval_term(&p.value, env, out);
+            writeln!(out, "{}", display(&value)).expect("write to print sink");
+            value
+        }
+
+        other => panic!("eval => unsupported term {other:?}"),
+    }
+}
+
+fn eval_binary(op: BinaryOp, lhs: Value, rhs: Value) -> Value {
+    match (op, lhs, rhs) {
+        (BinaryOp::Add, Value::Int(x), Value::Int(z)) => Value::Int(x + z),
+        (BinaryOp::Add, Value::Str(x), Value::Str(z)) => Value::Str(x + &z),
+        (BinaryOp::Sub, Value::Int(x), Value::Int(z)) => Value::Int(x - z),
+        (BinaryOp::Mul, Value::Int(x), Value::Int(z)) => Value::Int(x * z),
+        (BinaryOp::Div, Value::Int(x), Value::Int(z)) => Value::Int(x / z),
+        (BinaryOp::Rem, Value::Int(x), Value::Int(z)) => Value::Int(x % z),
+        (BinaryOp::Lt, Value::Int(x), Value::Int(z)) => Value::Bool(x < z),
+        (BinaryOp::Gt, Value::Int(x), Value::Int(z)) => Value::Bool(x > z),
+        (BinaryOp::Lte, Value::Int(x), Value::Int(z)) => Value::Bool(x <= z),
+        (BinaryOp::Gte, Value::Int(x), Value::Int(z)) => Value::Bool(x >= z),
+        (BinaryOp::Eq, x, z) => Value::Bool(x == z),
+        (BinaryOp::Neq, x, z) => Value::Bool(x != z),
+        (BinaryOp::And, Value::Bool(x), Value::Bool(z)) => Value::Bool(x && z),
+        (BinaryOp::Or, Value::Bool(x), Value::Bool(z)) => Value::Bool(x || z),
+        (op, lhs, rhs) => panic!("{op:?} => invalid operands {lhs:?}, {rhs:?}"),
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Int(i) => i.to_string(),
+        Value::Str(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Binary, BinaryOp, Bool, If, Int, Let, Str, Var};
+
+    fn run(term: Term) -> Value {
+        let mut env = Env::default();
+        let mut out = Vec::new();
+        eval_term(&term, &mut env, &mut out)
+    }
+
+    #[test]
+    fn eval_term_evaluates_literals() {
+        assert_eq!(run(Term::Int(Int { value: 42 })), Value::Int(42));
+        assert_eq!(
+            run(Term::Str(Str {
+                value: "hi".to_string(),
+            })),
+            Value::Str("hi".to_string())
+        );
+        assert_eq!(run(Term::Bool(Bool { value: true })), Value::Bool(true));
+    }
+
+    #[test]
+    fn eval_binary_adds_ints_and_concatenates_strings() {
+        assert_eq!(
+            eval_binary(BinaryOp::Add, Value::Int(1), Value::Int(2)),
+            Value::Int(3)
+        );
+        assert_eq!(
+            eval_binary(
+                BinaryOp::Add,
+                Value::Str("foo".to_string()),
+                Value::Str("bar".to_string())
+            ),
+            Value::Str("foobar".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_binary_compares_and_combines_bools() {
+        assert_eq!(
+            eval_binary(BinaryOp::Gt, Value::Int(2), Value::Int(1)),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval_binary(BinaryOp::And, Value::Bool(true), Value::Bool(false)),
+            Value::Bool(false)
+        );
+    }
+
+    #[test]
+    fn var_resolves_through_let() {
+        // let x = 1 + 2; x
+        let term = Term::Let(Let {
+            name: "x".to_string(),
+            value: Box::new(Term::Binary(Binary {
+                lhs: Box::new(Term::Int(Int { value: 1 })),
+                op: BinaryOp::Add,
+                rhs: Box::new(Term::Int(Int { value: 2 })),
+            })),
+            next: Box::new(Term::Var(Var {
+                name: "x".to_string(),
+            })),
+        });
+
+        assert_eq!(run(term), Value::Int(3));
+    }
+
+    #[test]
+    fn if_picks_a_branch_based_on_a_let_bound_condition() {
+        // let cond = 1 > 0; if cond then "yes" else "no"
+        let term = Term::Let(Let {
+            name: "cond".to_string(),
+            value: Box::new(Term::Binary(Binary {
+                lhs: Box::new(Term::Int(Int { value: 1 })),
+                op: BinaryOp::Gt,
+                rhs: Box::new(Term::Int(Int { value: 0 })),
+            })),
+            next: Box::new(Term::If(If {
+                condition: Box::new(Term::Var(Var {
+                    name: "cond".to_string(),
+                })),
+                then: Box::new(Term::Str(Str {
+                    value: "yes".to_string(),
+                })),
+                otherwise: Box::new(Term::Str(Str {
+                    value: "no".to_string(),
+                })),
+            })),
+        });
+
+        assert_eq!(run(term), Value::Str("yes".to_string()));
+    }
+}
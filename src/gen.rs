@@ -4,11 +4,45 @@ use std::io::Write;
 
 type GenericResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
 use crate::ast::{Binary, File as AstRoot, Term};
+use crate::backend::Backend;
+use crate::error::{Diagnostic, Label, Span};
+use crate::scope::Scope;
+use crate::typecheck;
 
 const STR: u8 = 0xca;
 const INT: u8 = 0xfe;
 const MAYBE: u8 = 0xba;
 
+fn c_operator(op: crate::ast::BinaryOp) -> &'static str {
+    use crate::ast::BinaryOp::*;
+    match op {
+        Add => "+",
+        Sub => "-",
+        Mul => "*",
+        Div => "/",
+        Rem => "%",
+        Eq => "==",
+        Neq => "!=",
+        Lt => "<",
+        Gt => ">",
+        Lte => "<=",
+        Gte => ">=",
+        And => "&&",
+        Or => "||",
+    }
+}
+
+/// Undoes `phonk!`'s `format!("{:?}", s.value)` debug-quoting to recover the
+/// original string's text.
+fn unquote(debug_quoted: &str) -> String {
+    debug_quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(debug_quoted)
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\")
+}
+
 #[derive(Default)]
 pub struct State {
     constants: HashMap<usize, (String, String)>,
@@ -16,6 +50,8 @@ pub struct State {
     print_queue: Vec<usize>,
     runtime_queue: HashMap<usize, String>,
     it: usize,
+    scope: Scope,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl State {
@@ -29,6 +65,18 @@ impl State {
                 *p.value
             }
 
+            // A bare `Var` carries no type information of its own, so the
+            // `loveint!`/`loveintcomp!`/Add matches below would never see a
+            // `Term::Int`/`Term::Str` for a bound name. Resolve it through
+            // `scope`/`constants` to the literal it's bound to first, the
+            // way `eval::eval_term` resolves through `Env` before matching.
+            Term::Var(v) => {
+                let resolved = self.resolve_var(&v);
+                self.inspect(&resolved);
+
+                resolved
+            }
+
             _ => {
                 self.inspect(&term);
 
@@ -37,6 +85,51 @@ impl State {
         }
     }
 
+    /// Looks `v` up through `scope` to the constant id `inspect` gave its
+    /// bound value, then reconstructs the literal `Term` that constant
+    /// holds. Falls back to returning `v` unresolved (e.g. unbound names,
+    /// or values that were never reduced to a known constant kind).
+    fn resolve_var(self: &Self, v: &crate::ast::Var) -> Term {
+        let Some(id) = self.scope.resolve(&v.name) else {
+            return Term::Var(v.clone());
+        };
+
+        match self.constants.get(&id) {
+            Some((kind, value)) if kind == "int" => Term::Int(crate::ast::Int {
+                value: value
+                    .parse()
+                    .expect("constants only ever store a literal int's own Display form"),
+            }),
+
+            Some((kind, value)) if kind == "char*" => Term::Str(crate::ast::Str {
+                value: unquote(value),
+            }),
+
+            Some((kind, value)) if kind == "char" => Term::Bool(crate::ast::Bool {
+                value: value == "true",
+            }),
+
+            _ => Term::Var(v.clone()),
+        }
+    }
+
+    /// Records a codegen-time type mismatch as a `Diagnostic` instead of
+    /// aborting, and leaves a placeholder `int` constant at `self.it` so
+    /// the rest of `write` still has something to emit for this id.
+    /// `typecheck` should already have rejected the program by the time
+    /// `generate` reaches one of these match arms, but codegen doesn't
+    /// trust that blindly — a bug in typecheck shouldn't take the process
+    /// down with it.
+    fn report_mismatch(self: &mut Self, code: &'static str, message: String, at: &Term) -> usize {
+        self.diagnostics
+            .push(Diagnostic::new(code, message, Label::new(Span::of(at), "here")));
+        self.constants
+            .insert(self.it, ("int".to_string(), "0".to_string()));
+        self.types.insert(self.it, INT);
+
+        self.it
+    }
+
     fn inspect(self: &mut Self, term: &Term) -> usize {
         self.it += 1;
 
@@ -63,7 +156,13 @@ impl State {
                         int!($it, x.value $op z.value);
                     }
 
-                    what => panic!("{} => Just ints. found {what:?}", $nm),
+                    what => {
+                        self.report_mismatch(
+                            "E0101",
+                            format!("{} expects two ints, found {what:?}", $nm),
+                            &$binary.lhs,
+                        );
+                    }
                 }
             };
         }
@@ -75,7 +174,13 @@ impl State {
                         maybe!($it, x.value $op z.value);
                     }
 
-                    _ => panic!(concat!($nm, "=> Just ints.")),
+                    what => {
+                        self.report_mismatch(
+                            "E0102",
+                            format!("{} expects two ints, found {what:?}", $nm),
+                            &$binary.lhs,
+                        );
+                    }
                 }
             };
         }
@@ -97,23 +202,31 @@ impl State {
 
             Term::If(comp) => match self.bag_or_die(*comp.condition.clone()) {
                 Term::Bool(b) => {
-                    let res = if b.value {
+                    self.scope.push();
+                    if b.value {
                         self.inspect(&comp.then)
                     } else {
                         self.inspect(&comp.otherwise)
                     };
-
-                    panic!("{}", res);
+                    self.scope.pop();
                 }
                 t @ Term::Binary(_) => {
                     let res = self.inspect(&t);
+                    self.scope.push();
                     if self.constants.get(&res).unwrap().1 == "true" {
                         self.inspect(&comp.then)
                     } else {
                         self.inspect(&comp.otherwise)
                     };
+                    self.scope.pop();
+                }
+                what => {
+                    self.report_mismatch(
+                        "E0006",
+                        format!("If condition must be a boolean or binary expression, found {what:?}"),
+                        &comp.condition,
+                    );
                 }
-                what => panic!("If => Just boolean or binary. found {what:?}"),
             },
 
             Term::Binary(binary) => match binary.op {
@@ -129,7 +242,13 @@ impl State {
                         phonk!(self.it, format!("{:?}", s.value + &s2.value));
                     }
 
-                    what => panic!("Add => Just ints and strings. found {what:?}"),
+                    what => {
+                        self.report_mismatch(
+                            "E0001",
+                            format!("Add expects two ints or two strings, found {what:?}"),
+                            &binary.lhs,
+                        );
+                    }
                 },
 
                 crate::ast::BinaryOp::Div => loveint!(self.it, binary, "Div", %),
@@ -141,7 +260,10 @@ impl State {
                 crate::ast::BinaryOp::Lte => loveintcomp!(self.it, binary, "Lte", >=),
                 crate::ast::BinaryOp::Gte => loveintcomp!(self.it, binary, "Gte", <=),
 
-                crate::ast::BinaryOp::Eq => match (*binary.lhs.clone(), *binary.rhs.clone()) {
+                crate::ast::BinaryOp::Eq => match (
+                    self.bag_or_die(*binary.lhs.clone()),
+                    self.bag_or_die(*binary.rhs.clone()),
+                ) {
                     (Term::Int(x), Term::Int(z)) => {
                         maybe!(self.it, x.value == z.value);
                     }
@@ -156,10 +278,19 @@ impl State {
                         maybe!(self.it, b.value == b2.value);
                     }
 
-                    _ => panic!("Eq => Invalid types!"),
+                    (lhs, rhs) => {
+                        self.report_mismatch(
+                            "E0004",
+                            format!("Eq expects two operands of the same type, found {lhs:?} and {rhs:?}"),
+                            &binary.lhs,
+                        );
+                    }
                 },
 
-                crate::ast::BinaryOp::Neq => match (*binary.lhs.clone(), *binary.rhs.clone()) {
+                crate::ast::BinaryOp::Neq => match (
+                    self.bag_or_die(*binary.lhs.clone()),
+                    self.bag_or_die(*binary.rhs.clone()),
+                ) {
                     (Term::Int(x), Term::Int(z)) => {
                         maybe!(self.it, x.value != z.value);
                     }
@@ -174,60 +305,160 @@ impl State {
                         maybe!(self.it, b.value != b2.value);
                     }
 
-                    _ => todo!(),
+                    (lhs, rhs) => {
+                        self.report_mismatch(
+                            "E0004",
+                            format!("Neq expects two operands of the same type, found {lhs:?} and {rhs:?}"),
+                            &binary.lhs,
+                        );
+                    }
                 },
 
-                crate::ast::BinaryOp::And => match (*binary.lhs.clone(), *binary.rhs.clone()) {
+                crate::ast::BinaryOp::And => match (
+                    self.bag_or_die(*binary.lhs.clone()),
+                    self.bag_or_die(*binary.rhs.clone()),
+                ) {
                     (Term::Bool(b), Term::Bool(b2)) => {
                         maybe!(self.it, b.value && b2.value);
                     }
 
-                    _ => panic!("Just bools are allowed."),
+                    (lhs, rhs) => {
+                        self.report_mismatch(
+                            "E0005",
+                            format!("And expects two bools, found {lhs:?} and {rhs:?}"),
+                            &binary.lhs,
+                        );
+                    }
                 },
 
-                crate::ast::BinaryOp::Or => match (*binary.lhs.clone(), *binary.rhs.clone()) {
+                crate::ast::BinaryOp::Or => match (
+                    self.bag_or_die(*binary.lhs.clone()),
+                    self.bag_or_die(*binary.rhs.clone()),
+                ) {
                     (Term::Bool(b), Term::Bool(b2)) => {
                         maybe!(self.it, b.value || b2.value);
                     }
 
-                    _ => panic!("Just bools are allowed."),
+                    (lhs, rhs) => {
+                        self.report_mismatch(
+                            "E0005",
+                            format!("Or expects two bools, found {lhs:?} and {rhs:?}"),
+                            &binary.lhs,
+                        );
+                    }
                 },
             },
 
+            Term::Let(l) => {
+                let id = self.inspect(&l.value);
+                if !self.constants.contains_key(&id) {
+                    self.runtime_queue.insert(id, self.render_runtime_expr(&l.value));
+                }
+                self.scope.bind(l.name.clone(), id);
+
+                return self.inspect(&l.next);
+            }
+
+            Term::Var(v) => {
+                return self.scope.resolve(&v.name).unwrap_or_else(|| {
+                    self.diagnostics.push(Diagnostic::new(
+                        "E0008",
+                        format!("unbound name {:?}", v.name),
+                        Label::new(Span::of(term), "not found in this scope"),
+                    ));
+                    self.it
+                });
+            }
+
             _ => {}
         }
         return self.it;
     }
 
-    pub fn write(self: Self) -> GenericResult<()> {
-        let mut output = File::create("output.c")?;
+    /// Renders `term` as a C expression for `runtime_queue`, the way
+    /// `CBackend::emit_runtime_assign` splices it straight into an
+    /// assignment. Used for `Let` values that don't fold down to a known
+    /// constant, so the queue holds real target syntax instead of a
+    /// `{:?}`-formatted `Term`.
+    fn render_runtime_expr(self: &Self, term: &Term) -> String {
+        match term {
+            Term::Int(i) => i.value.to_string(),
+            Term::Str(s) => format!("{:?}", s.value),
+            Term::Bool(b) => (if b.value { "1" } else { "0" }).to_string(),
+
+            Term::Var(v) => match self.scope.resolve(&v.name) {
+                Some(id) => format!("v_{id}"),
+                None => format!("/* unbound {:?} */ 0", v.name),
+            },
+
+            Term::Binary(b) => format!(
+                "({} {} {})",
+                self.render_runtime_expr(&b.lhs),
+                c_operator(b.op),
+                self.render_runtime_expr(&b.rhs),
+            ),
+
+            other => format!("/* unsupported runtime expr {other:?} */ 0"),
+        }
+    }
 
-        writeln!(output, "{}", include_str!("yamero.c"))?;
+    pub fn write(self: Self, mut backend: Box<dyn Backend>) -> GenericResult<()> {
+        backend.preamble();
 
-        for (j, (k, v)) in self.constants {
-            writeln!(output, "{} v_{} = {};", k, j, v)?
+        for (j, (k, v)) in &self.constants {
+            backend.emit_constant(*j, k, v);
         }
 
-        for (j, k) in self.types {
-            writeln!(output, "const Kind t_{j} = {k};")?;
+        for (j, k) in &self.types {
+            backend.emit_kind(*j, *k);
         }
 
-        writeln!(output, "int main(void) {{")?;
+        backend.begin_body();
 
-        for (id, expr) in self.runtime_queue {
-            writeln!(output, "v_{id} = {expr};")?;
+        for (id, expr) in &self.runtime_queue {
+            backend.emit_runtime_assign(*id, expr);
         }
 
-        for item in self.print_queue {
-            writeln!(output, "p((void*)&v_{item}, t_{item});")?;
+        for item in &self.print_queue {
+            backend.emit_print(*item);
         }
 
-        writeln!(output, "return 0;}}")?;
+        let path = format!("output.{}", backend.extension());
+        let rendered = backend.finish();
+
+        let mut output = File::create(path)?;
+        writeln!(output, "{rendered}")?;
 
         Ok(())
     }
 
-    pub fn generate(self: &mut Self, source: AstRoot) -> GenericResult<()> {
+    pub fn generate(self: &mut Self, source: AstRoot) -> Result<(), Vec<Diagnostic>> {
+        let AstRoot { name, expression } = source;
+        let source = AstRoot {
+            name,
+            expression: crate::optimize::optimize(expression),
+        };
+
+        // `typecheck` returns an `Annotated` type map alongside the `Result`,
+        // but `inspect` below doesn't consume it and keeps re-deriving its
+        // own checks (the `loveint!`/`loveintcomp!`/Add/Eq/Neq/And/Or
+        // arms). That's not an oversight: `Annotated::types` is keyed by
+        // `typecheck::walk`'s own pre-order counter, which doesn't line up
+        // with `inspect`'s. `walk` always visits both of an `If`'s branches
+        // (it has to, to validate them both), while `inspect` is a partial
+        // evaluator that only ever descends into the branch its condition
+        // actually took. `walk` also numbers every `Print` node ahead of its
+        // value, while `inspect` gives a top-level `Print` no id of its own,
+        // numbers a `Print` operand after its value, and doesn't descend
+        // into a `Print` it is handed directly. The two counters fall out of
+        // step at the first such node, and a lookup into `types` from there
+        // on would silently name the wrong `Term`. Soundly sharing one
+        // annotated walk would mean giving `Annotated` a non-positional key
+        // — a bigger redesign than this fix, so `inspect` stays the runtime
+        // source of truth and `?` here just keeps codegen from ever running
+        // over an ill-typed tree in the first place.
+        typecheck::typecheck(&source)?;
+
         match source.expression {
             crate::ast::Term::Error(_) => todo!(),
             crate::ast::Term::Int(_) => todo!(),
@@ -235,7 +466,9 @@ impl State {
             crate::ast::Term::Call(_) => todo!(),
             crate::ast::Term::Binary(_) => todo!(),
             crate::ast::Term::Function(_) => todo!(),
-            crate::ast::Term::Let(_) => todo!(),
+            crate::ast::Term::Let(l) => {
+                self.inspect(&crate::ast::Term::Let(l));
+            }
             crate::ast::Term::If(_) => todo!(),
             crate::ast::Term::Print(what) => {
                 let it = self.inspect(&what.value);
@@ -246,9 +479,15 @@ impl State {
             crate::ast::Term::Second(_) => todo!(),
             crate::ast::Term::Bool(_) => todo!(),
             crate::ast::Term::Tuple(_) => todo!(),
-            crate::ast::Term::Var(_) => todo!(),
+            crate::ast::Term::Var(v) => {
+                self.inspect(&crate::ast::Term::Var(v));
+            }
         }
 
-        Ok(())
+        if self.diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
     }
 }
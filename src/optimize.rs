@@ -0,0 +1,272 @@
+//! Algebraic simplification / peephole optimization over `Term` trees.
+//!
+//! Runs on the AST before codegen (wired in by `gen::State::generate`) and
+//! applies identity rewrites (`x + 0 -> x`, `0 + x -> x`, `x - 0 -> x`,
+//! `x * 1 -> x`, `1 * x -> x`, `x * 0 -> 0`, `x - x -> 0`), constant-folding
+//! any `Add`/`Sub`/`Mul` subtree whose leaves are all literals. `Div`, `Rem`,
+//! comparisons and `And`/`Or` get their operands optimized but are not
+//! folded themselves. `gen::inspect` only ever
+//! folded two adjacent `Term::Int`s, so a chain like
+//! `arg + 0 - arg * 1 + arg + 1 + arg + 2 + arg + 3 - arg * 3 - 6` never
+//! collapsed; here an additive chain of `Add`/`Sub` is flattened into a list
+//! of signed terms, every constant term is summed into one literal, and
+//! every `Var`/`Var * Int`/`Int * Var` term referring to the same name has
+//! its (possibly negative) coefficients summed, and any other term that
+//! occurs once added and once subtracted cancels out, before a minimal tree
+//! is rebuilt from what's left. The pass is idempotent and leaves
+//! non-arithmetic terms untouched.
+
+use crate::ast::{Binary, BinaryOp, If, Int, Let, Print, Term, Var};
+
+pub fn optimize(term: Term) -> Term {
+    match term {
+        Term::Binary(b) => match b.op {
+            BinaryOp::Add | BinaryOp::Sub => optimize_additive(Term::Binary(b)),
+
+            BinaryOp::Mul => {
+                let lhs = optimize(*b.lhs);
+                let rhs = optimize(*b.rhs);
+                simplify_mul(lhs, rhs)
+            }
+
+            op => {
+                let lhs = optimize(*b.lhs);
+                let rhs = optimize(*b.rhs);
+                Term::Binary(Binary {
+                    lhs: Box::new(lhs),
+                    op,
+                    rhs: Box::new(rhs),
+                })
+            }
+        },
+
+        Term::If(c) => Term::If(If {
+            condition: Box::new(optimize(*c.condition)),
+            then: Box::new(optimize(*c.then)),
+            otherwise: Box::new(optimize(*c.otherwise)),
+        }),
+
+        Term::Let(l) => Term::Let(Let {
+            name: l.name,
+            value: Box::new(optimize(*l.value)),
+            next: Box::new(optimize(*l.next)),
+        }),
+
+        Term::Print(p) => Term::Print(Print {
+            value: Box::new(optimize(*p.value)),
+        }),
+
+        other => other,
+    }
+}
+
+fn simplify_mul(lhs: Term, rhs: Term) -> Term {
+    match (&lhs, &rhs) {
+        (_, Term::Int(Int { value: 1 })) => lhs,
+        (Term::Int(Int { value: 1 }), _) => rhs,
+        (_, Term::Int(Int { value: 0 })) | (Term::Int(Int { value: 0 }), _) => {
+            Term::Int(Int { value: 0 })
+        }
+        (Term::Int(x), Term::Int(z)) => Term::Int(Int {
+            value: x.value * z.value,
+        }),
+        _ => Term::Binary(Binary {
+            lhs: Box::new(lhs),
+            op: BinaryOp::Mul,
+            rhs: Box::new(rhs),
+        }),
+    }
+}
+
+/// Flattens the `Add`/`Sub` chain rooted at `term` into signed terms, sums
+/// the constant ones, sums every same-named `Var`'s (possibly negative)
+/// coefficient across all its occurrences (a bare `Var` counts as `1`, and
+/// `Var * Int`/`Int * Var` counts as that int), cancels any other term that
+/// appears with both signs, and rebuilds a minimal tree from whatever is
+/// left with a nonzero coefficient.
+fn optimize_additive(term: Term) -> Term {
+    let mut terms = Vec::new();
+    flatten(term, true, &mut terms);
+
+    let mut constant: i64 = 0;
+    let mut var_coeffs: Vec<(String, i64)> = Vec::new();
+    let mut others: Vec<(bool, Term)> = Vec::new();
+
+    for (sign, t) in terms {
+        match as_var_coefficient(&t) {
+            Some((name, coeff)) => {
+                let signed = if sign { coeff } else { -coeff };
+                match var_coeffs.iter_mut().find(|(n, _)| *n == name) {
+                    Some(entry) => entry.1 += signed,
+                    None => var_coeffs.push((name, signed)),
+                }
+            }
+            None => match t {
+                Term::Int(i) => constant += if sign { i.value } else { -i.value },
+                other => match others.iter().position(|(s, o)| *s != sign && *o == other) {
+                    Some(pos) => {
+                        others.remove(pos);
+                    }
+                    None => others.push((sign, other)),
+                },
+            },
+        }
+    }
+
+    let mut pieces: Vec<(bool, Term)> = var_coeffs
+        .into_iter()
+        .filter(|(_, coeff)| *coeff != 0)
+        .map(|(name, coeff)| {
+            let var = Term::Var(Var { name });
+            let piece = if coeff.abs() == 1 {
+                var
+            } else {
+                Term::Binary(Binary {
+                    lhs: Box::new(var),
+                    op: BinaryOp::Mul,
+                    rhs: Box::new(Term::Int(Int {
+                        value: coeff.abs(),
+                    })),
+                })
+            };
+            (coeff > 0, piece)
+        })
+        .collect();
+    pieces.extend(others);
+
+    let mut result = if constant != 0 || pieces.is_empty() {
+        Some(Term::Int(Int { value: constant }))
+    } else {
+        None
+    };
+
+    for (sign, t) in pieces {
+        result = Some(match result {
+            None if sign => t,
+            None => Term::Binary(Binary {
+                lhs: Box::new(Term::Int(Int { value: 0 })),
+                op: BinaryOp::Sub,
+                rhs: Box::new(t),
+            }),
+            Some(acc) => Term::Binary(Binary {
+                lhs: Box::new(acc),
+                op: if sign { BinaryOp::Add } else { BinaryOp::Sub },
+                rhs: Box::new(t),
+            }),
+        });
+    }
+
+    result.unwrap()
+}
+
+/// Recognizes `term` as `coefficient * name`: a bare `Var` is coefficient
+/// `1`, and `Var * Int`/`Int * Var` is that int — the two shapes a `Var`
+/// term can take after `simplify_mul`'s identity rewrites.
+fn as_var_coefficient(term: &Term) -> Option<(String, i64)> {
+    match term {
+        Term::Var(v) => Some((v.name.clone(), 1)),
+        Term::Binary(Binary {
+            lhs,
+            op: BinaryOp::Mul,
+            rhs,
+        }) => match (lhs.as_ref(), rhs.as_ref()) {
+            (Term::Var(v), Term::Int(i)) => Some((v.name.clone(), i.value)),
+            (Term::Int(i), Term::Var(v)) => Some((v.name.clone(), i.value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn flatten(term: Term, sign: bool, out: &mut Vec<(bool, Term)>) {
+    match term {
+        Term::Binary(b) => match b.op {
+            BinaryOp::Add => {
+                flatten(*b.lhs, sign, out);
+                flatten(*b.rhs, sign, out);
+            }
+            BinaryOp::Sub => {
+                flatten(*b.lhs, sign, out);
+                flatten(*b.rhs, !sign, out);
+            }
+            op => {
+                // `x * 1` can simplify to an `Add`/`Sub` chain; flatten it
+                // here too so a second pass has nothing left to merge.
+                let t = optimize(Term::Binary(Binary {
+                    lhs: b.lhs,
+                    op,
+                    rhs: b.rhs,
+                }));
+                if matches!(t, Term::Binary(Binary { op: BinaryOp::Add | BinaryOp::Sub, .. })) {
+                    flatten(t, sign, out);
+                } else {
+                    out.push((sign, t));
+                }
+            }
+        },
+        other => out.push((sign, optimize(other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Term {
+        Term::Var(Var {
+            name: name.to_string(),
+        })
+    }
+
+    fn int(value: i64) -> Term {
+        Term::Int(Int { value })
+    }
+
+    fn bin(lhs: Term, op: BinaryOp, rhs: Term) -> Term {
+        Term::Binary(Binary {
+            lhs: Box::new(lhs),
+            op,
+            rhs: Box::new(rhs),
+        })
+    }
+
+    #[test]
+    fn collapses_the_motivating_chain_to_zero() {
+        // arg + 0 - arg * 1 + arg + 1 + arg + 2 + arg + 3 - arg * 3 - 6
+        let chain = [
+            (BinaryOp::Add, var("arg")),
+            (BinaryOp::Add, int(0)),
+            (BinaryOp::Sub, bin(var("arg"), BinaryOp::Mul, int(1))),
+            (BinaryOp::Add, var("arg")),
+            (BinaryOp::Add, int(1)),
+            (BinaryOp::Add, var("arg")),
+            (BinaryOp::Add, int(2)),
+            (BinaryOp::Add, var("arg")),
+            (BinaryOp::Add, int(3)),
+            (BinaryOp::Sub, bin(var("arg"), BinaryOp::Mul, int(3))),
+            (BinaryOp::Sub, int(6)),
+        ];
+
+        let mut term = chain[0].1.clone();
+        for (op, rhs) in chain.into_iter().skip(1) {
+            term = bin(term, op, rhs);
+        }
+
+        assert_eq!(optimize(term), int(0));
+    }
+
+    #[test]
+    fn keeps_a_nonzero_residual_coefficient() {
+        // arg + arg + arg - 1 -> -1 + arg * 3
+        let term = bin(
+            bin(bin(var("arg"), BinaryOp::Add, var("arg")), BinaryOp::Add, var("arg")),
+            BinaryOp::Sub,
+            int(1),
+        );
+
+        assert_eq!(
+            optimize(term),
+            bin(int(-1), BinaryOp::Add, bin(var("arg"), BinaryOp::Mul, int(3)))
+        );
+    }
+}
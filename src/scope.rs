@@ -0,0 +1,42 @@
+//! Lexical scope tracking for `State::inspect`.
+//!
+//! A [`Scope`] is a stack of frames, each mapping a bound name to the
+//! constant id `inspect` assigned its value. `inspect` pushes a frame around
+//! the `If` branch it descends into, and a `Let` binds its name into the
+//! innermost frame; function bodies get no frame of their own. Lookups walk
+//! the stack from the top down so that inner frames shadow outer ones.
+
+use std::collections::HashMap;
+
+pub struct Scope {
+    frames: Vec<HashMap<String, usize>>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope {
+            frames: vec![HashMap::new()],
+        }
+    }
+}
+
+impl Scope {
+    pub fn push(self: &mut Self) {
+        self.frames.push(HashMap::new());
+    }
+
+    pub fn pop(self: &mut Self) {
+        self.frames.pop();
+    }
+
+    pub fn bind(self: &mut Self, name: String, id: usize) {
+        self.frames
+            .last_mut()
+            .expect("scope always has at least one frame")
+            .insert(name, id);
+    }
+
+    pub fn resolve(self: &Self, name: &str) -> Option<usize> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name).copied())
+    }
+}
@@ -0,0 +1,240 @@
+//! A standalone type-checking pass over the AST.
+//!
+//! `typecheck` walks the tree once, before `generate` ever runs, and infers
+//! a [`Type`] for every `Term` it can type (`Call`, `Function`, `Tuple`,
+//! `First`, `Second`, `Error` and unbound `Var`s get none). Unlike the
+//! macros in `gen` (`loveint!`,
+//! `loveintcomp!`, `phonk!`), it never aborts on the first mismatch — every
+//! mistake found along the way becomes a [`Diagnostic`] and they're all
+//! returned together, so a caller can report every error in a program in one
+//! pass. `generate` runs this first and bails via `?` on any error, so by
+//! the time `inspect` walks the tree it's already known well-typed; `inspect`
+//! still re-derives its own checks rather than consuming [`Annotated`]
+//! directly; see the comment on `generate`'s call in `gen.rs` for why.
+
+use std::collections::HashMap;
+
+use crate::ast::{BinaryOp, File as AstRoot, Term};
+use crate::error::{Diagnostic, Label, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Str,
+    Bool,
+    /// The `char`-backed boolean-ish result of comparisons, mirroring
+    /// `gen::MAYBE`.
+    Maybe,
+}
+
+/// The inferred type of every `Term` that got one, keyed by `walk`'s own
+/// pre-order position counter.
+///
+/// That counter doesn't line up with the one `State::inspect` uses: `walk`
+/// always visits both of an `If`'s branches to validate them, while
+/// `inspect` is a partial evaluator that only ever descends into the
+/// branch the condition actually took, and the two number `Print` nodes
+/// differently. Consuming `types` by id from `inspect` would name the wrong
+/// `Term` — see the comment on `generate`'s `typecheck::typecheck` call in
+/// `gen.rs`.
+#[derive(Default)]
+pub struct Annotated {
+    pub types: HashMap<usize, Type>,
+}
+
+pub fn typecheck(root: &AstRoot) -> Result<Annotated, Vec<Diagnostic>> {
+    let mut errors = Vec::new();
+    let mut annotated = Annotated::default();
+    let mut it = 0usize;
+    let mut scope = TypeScope::default();
+
+    walk(&root.expression, &mut it, &mut scope, &mut annotated, &mut errors);
+
+    if errors.is_empty() {
+        Ok(annotated)
+    } else {
+        Err(errors)
+    }
+}
+
+/// A stack of name->type frames, mirroring `scope::Scope` but carrying the
+/// `Type` a name was bound to instead of a codegen constant id — `walk`
+/// needs this so a `Let`-bound name round-trips its type through any later
+/// `Var` reference instead of going untyped.
+#[derive(Default)]
+struct TypeScope {
+    frames: Vec<HashMap<String, Type>>,
+}
+
+impl TypeScope {
+    fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    fn bind(&mut self, name: String, ty: Type) {
+        if self.frames.is_empty() {
+            self.frames.push(HashMap::new());
+        }
+        self.frames.last_mut().unwrap().insert(name, ty);
+    }
+
+    fn resolve(&self, name: &str) -> Option<Type> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name).copied())
+    }
+}
+
+fn mismatch(code: &'static str, message: impl Into<String>, lhs: &Term, rhs: &Term) -> Diagnostic {
+    Diagnostic::new(code, message, Label::new(Span::of(lhs), "here"))
+        .with_note(Label::new(Span::of(rhs), "and here"))
+}
+
+fn walk(
+    term: &Term,
+    it: &mut usize,
+    scope: &mut TypeScope,
+    out: &mut Annotated,
+    errors: &mut Vec<Diagnostic>,
+) -> Option<Type> {
+    *it += 1;
+    let id = *it;
+
+    let ty = match term {
+        Term::Int(_) => Some(Type::Int),
+        Term::Str(_) => Some(Type::Str),
+        Term::Bool(_) => Some(Type::Bool),
+
+        Term::Binary(b) => {
+            let lhs = walk(&b.lhs, it, scope, out, errors);
+            let rhs = walk(&b.rhs, it, scope, out, errors);
+
+            match b.op {
+                BinaryOp::Add => match (lhs, rhs) {
+                    (Some(Type::Int), Some(Type::Int)) => Some(Type::Int),
+                    (Some(Type::Str), Some(Type::Str)) => Some(Type::Str),
+                    _ => {
+                        errors.push(mismatch(
+                            "E0001",
+                            "Add expects two ints or two strings",
+                            &b.lhs,
+                            &b.rhs,
+                        ));
+                        None
+                    }
+                },
+
+                BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                    match (lhs, rhs) {
+                        (Some(Type::Int), Some(Type::Int)) => Some(Type::Int),
+                        _ => {
+                            errors.push(mismatch(
+                                "E0002",
+                                format!("{:?} expects two ints", b.op),
+                                &b.lhs,
+                                &b.rhs,
+                            ));
+                            None
+                        }
+                    }
+                }
+
+                BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Lte | BinaryOp::Gte => {
+                    match (lhs, rhs) {
+                        (Some(Type::Int), Some(Type::Int)) => Some(Type::Maybe),
+                        _ => {
+                            errors.push(mismatch(
+                                "E0003",
+                                format!("{:?} expects two ints", b.op),
+                                &b.lhs,
+                                &b.rhs,
+                            ));
+                            None
+                        }
+                    }
+                }
+
+                BinaryOp::Eq | BinaryOp::Neq => match (lhs, rhs) {
+                    (Some(a), Some(z)) if a == z => Some(Type::Maybe),
+                    _ => {
+                        errors.push(mismatch(
+                            "E0004",
+                            format!("{:?} expects two operands of the same type", b.op),
+                            &b.lhs,
+                            &b.rhs,
+                        ));
+                        None
+                    }
+                },
+
+                BinaryOp::And | BinaryOp::Or => match (lhs, rhs) {
+                    (Some(Type::Bool), Some(Type::Bool)) => Some(Type::Maybe),
+                    _ => {
+                        errors.push(mismatch(
+                            "E0005",
+                            format!("{:?} expects two bools", b.op),
+                            &b.lhs,
+                            &b.rhs,
+                        ));
+                        None
+                    }
+                },
+            }
+        }
+
+        Term::If(comp) => {
+            let cond = walk(&comp.condition, it, scope, out, errors);
+            if !matches!(cond, Some(Type::Bool) | Some(Type::Maybe)) {
+                errors.push(Diagnostic::new(
+                    "E0006",
+                    "If condition must be a boolean",
+                    Label::new(Span::of(&comp.condition), "expected a boolean here"),
+                ));
+            }
+
+            // Each branch gets its own frame: a `Let` inside `then` must not
+            // leak its binding into `otherwise` (or past the `If` itself).
+            scope.push();
+            let then_ty = walk(&comp.then, it, scope, out, errors);
+            scope.pop();
+
+            scope.push();
+            let else_ty = walk(&comp.otherwise, it, scope, out, errors);
+            scope.pop();
+
+            if then_ty != else_ty {
+                errors.push(mismatch(
+                    "E0007",
+                    "If branches must agree on type",
+                    &comp.then,
+                    &comp.otherwise,
+                ));
+            }
+
+            then_ty
+        }
+
+        Term::Let(l) => {
+            let value_ty = walk(&l.value, it, scope, out, errors);
+            if let Some(ty) = value_ty {
+                scope.bind(l.name.clone(), ty);
+            }
+
+            walk(&l.next, it, scope, out, errors)
+        }
+
+        Term::Print(p) => walk(&p.value, it, scope, out, errors),
+
+        Term::Var(v) => scope.resolve(&v.name),
+
+        _ => None,
+    };
+
+    if let Some(ty) = ty {
+        out.types.insert(id, ty);
+    }
+
+    ty
+}